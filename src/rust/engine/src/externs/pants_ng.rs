@@ -13,12 +13,102 @@ use pyo3::prelude::*;
 use pyo3::types::PyDict;
 
 use crate::externs::options::{
-    OptionValue, PyConfigSource, PyDictVal, PyOptionId, condense_dict_value_derivation,
-    condense_list_value_derivation, dict_into_py, into_py, py_object_to_val,
+    condense_dict_value_derivation, condense_list_value_derivation, dict_into_py, into_py,
+    py_object_to_val, OptionValue, PyConfigSource, PyDictVal, PyOptionId,
 };
 
 pyo3::import_exception!(pants.option.errors, ParseError);
 
+///
+/// Computes the Damerau-Levenshtein edit distance between `a` and `b`: the minimum number of
+/// single-character insertions, deletions, substitutions, or adjacent transpositions needed to
+/// turn `a` into `b`. Used to power "did you mean" suggestions for unrecognized option names.
+///
+fn damerau_levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (m, n) = (a.len(), b.len());
+
+    // `table[i][j]` is the edit distance between `a[..i]` and `b[..j]`.
+    let mut table = vec![vec![0usize; n + 1]; m + 1];
+    for (i, row) in table.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for j in 0..=n {
+        table[0][j] = j;
+    }
+
+    for i in 1..=m {
+        for j in 1..=n {
+            let substitution_cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (table[i - 1][j] + 1) // deletion
+                .min(table[i][j - 1] + 1) // insertion
+                .min(table[i - 1][j - 1] + substitution_cost); // substitution
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(table[i - 2][j - 2] + substitution_cost);
+                // transposition
+            }
+            table[i][j] = distance;
+        }
+    }
+    table[m][n]
+}
+
+///
+/// Finds the `known_names` entry closest to `name` by Damerau-Levenshtein distance, to power a
+/// "did you mean `X`?" suggestion. Only suggests a match within `max(1, name.len() / 3)` edits of
+/// `name`, to avoid suggesting something unrelated; ties are broken by preferring the
+/// lexicographically smallest candidate, so the result is deterministic.
+///
+fn suggest_option_name<'a>(
+    name: &str,
+    known_names: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    let max_distance = std::cmp::max(1, name.len() / 3);
+    known_names
+        .into_iter()
+        .filter(|&candidate| candidate != name)
+        .map(|candidate| (damerau_levenshtein_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by(|(d1, name1), (d2, name2)| d1.cmp(d2).then_with(|| name1.cmp(name2)))
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+///
+/// Appends a "did you mean `X`?" suggestion to `message` when `name` is a near miss for one of
+/// `known_option_names`, to help with typos like `--pytest-timeout` vs. `--pytest-timeouts`.
+/// Leaves `message` untouched when no close enough match exists.
+///
+/// `known_option_names` is supplied by the Python caller (which already owns the full registered
+/// option schema for the scope being parsed) rather than looked up here, since `OptionsReader`
+/// has no API of its own for enumerating the options registered in a scope.
+///
+fn append_suggestion(message: String, name: &str, known_option_names: &[String]) -> String {
+    match suggest_option_name(name, known_option_names.iter().map(String::as_str)) {
+        Some(suggestion) => format!("{message}\n\nDid you mean `{suggestion}`?"),
+        None => message,
+    }
+}
+
+///
+/// As `append_suggestion`, but both `name` and `known_option_names` are optional: callers that
+/// don't pass them (every existing caller, since this is a new, opt-in piece of the API) get
+/// `message` back unchanged, rather than being forced to start passing a name/known-names pair
+/// they don't have.
+///
+fn append_suggestion_if_known(
+    message: String,
+    name: Option<&str>,
+    known_option_names: Option<&[String]>,
+) -> String {
+    match (name, known_option_names) {
+        (Some(name), Some(known_option_names)) => {
+            append_suggestion(message, name, known_option_names)
+        }
+        _ => message,
+    }
+}
+
 pub(crate) fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<PyNgInvocation>()?;
     m.add_class::<PyNgOptionsReader>()?;
@@ -40,11 +130,14 @@ pub struct PyNgOptionsReader(pub OptionsReader);
 
 #[allow(clippy::type_complexity)]
 impl PyNgOptionsReader {
+    #[allow(clippy::too_many_arguments)]
     fn get_list<'py, T: ToOwned + ?Sized>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Vec<T::Owned>,
+        name: Option<&str>,
+        known_option_names: Option<&[String]>,
         getter: fn(
             &'py OptionsReader,
             &OptionId,
@@ -54,8 +147,9 @@ impl PyNgOptionsReader {
     where
         <T as ToOwned>::Owned: PartialEq,
     {
-        let opt_val =
-            getter(&self.0, &option_id.borrow().0, default).map_err(ParseError::new_err)?;
+        let opt_val = getter(&self.0, &option_id.borrow().0, default)
+            .map_err(|message| append_suggestion_if_known(message, name, known_option_names))
+            .map_err(ParseError::new_err)?;
         Ok((
             Some(opt_val.value),
             opt_val.source.rank() as isize,
@@ -101,107 +195,166 @@ impl PyNgOptionsReader {
         .map_err(ParseError::new_err)
     }
 
-    #[pyo3(signature = (option_id, default))]
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_bool<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Option<bool>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, bool>> {
         into_py(
             py,
-            self.0.parse_bool_optional(&option_id.borrow().0, default),
+            self.0
+                .parse_bool_optional(&option_id.borrow().0, default)
+                .map_err(|message| {
+                    append_suggestion_if_known(message, name, known_option_names.as_deref())
+                }),
         )
     }
 
-    #[pyo3(signature = (option_id, default))]
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_int<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Option<i64>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, i64>> {
         into_py(
             py,
-            self.0.parse_int_optional(&option_id.borrow().0, default),
+            self.0
+                .parse_int_optional(&option_id.borrow().0, default)
+                .map_err(|message| {
+                    append_suggestion_if_known(message, name, known_option_names.as_deref())
+                }),
         )
     }
 
-    #[pyo3(signature = (option_id, default))]
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_float<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Option<f64>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, f64>> {
         into_py(
             py,
-            self.0.parse_float_optional(&option_id.borrow().0, default),
+            self.0
+                .parse_float_optional(&option_id.borrow().0, default)
+                .map_err(|message| {
+                    append_suggestion_if_known(message, name, known_option_names.as_deref())
+                }),
         )
     }
 
-    #[pyo3(signature = (option_id, default))]
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_string<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Option<&str>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, String>> {
         into_py(
             py,
-            self.0.parse_string_optional(&option_id.borrow().0, default),
+            self.0
+                .parse_string_optional(&option_id.borrow().0, default)
+                .map_err(|message| {
+                    append_suggestion_if_known(message, name, known_option_names.as_deref())
+                }),
         )
     }
 
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_bool_list<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Vec<bool>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, Vec<bool>>> {
-        self.get_list::<bool>(py, option_id, default, |op, oid, def| {
-            op.parse_bool_list(oid, def)
-        })
+        self.get_list::<bool>(
+            py,
+            option_id,
+            default,
+            name,
+            known_option_names.as_deref(),
+            |op, oid, def| op.parse_bool_list(oid, def),
+        )
     }
 
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_int_list<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Vec<i64>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, Vec<i64>>> {
-        self.get_list::<i64>(py, option_id, default, |op, oid, def| {
-            op.parse_int_list(oid, def)
-        })
+        self.get_list::<i64>(
+            py,
+            option_id,
+            default,
+            name,
+            known_option_names.as_deref(),
+            |op, oid, def| op.parse_int_list(oid, def),
+        )
     }
 
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_float_list<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Vec<f64>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, Vec<f64>>> {
-        self.get_list::<f64>(py, option_id, default, |op, oid, def| {
-            op.parse_float_list(oid, def)
-        })
+        self.get_list::<f64>(
+            py,
+            option_id,
+            default,
+            name,
+            known_option_names.as_deref(),
+            |op, oid, def| op.parse_float_list(oid, def),
+        )
     }
 
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_string_list<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: Vec<String>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, Vec<String>>> {
-        self.get_list::<String>(py, option_id, default, |op, oid, def| {
-            op.parse_string_list(oid, def)
-        })
+        self.get_list::<String>(
+            py,
+            option_id,
+            default,
+            name,
+            known_option_names.as_deref(),
+            |op, oid, def| op.parse_string_list(oid, def),
+        )
     }
 
+    #[pyo3(signature = (option_id, default, name=None, known_option_names=None))]
     fn get_dict<'py>(
         &'py self,
         py: Python<'py>,
         option_id: &Bound<'_, PyOptionId>,
         default: &Bound<'_, PyDict>,
+        name: Option<&str>,
+        known_option_names: Option<Vec<String>>,
     ) -> PyResult<OptionValue<'py, PyDictVal>> {
         let default = default
             .items()
@@ -215,6 +368,9 @@ impl PyNgOptionsReader {
         let opt_val = self
             .0
             .parse_dict(&option_id.borrow().0, default)
+            .map_err(|message| {
+                append_suggestion_if_known(message, name, known_option_names.as_deref())
+            })
             .map_err(ParseError::new_err)?;
         let opt_val_py = dict_into_py(py, opt_val.value)?;
 
@@ -242,8 +398,8 @@ struct PyNgSourcePartition {
 impl PartialEq for PyNgSourcePartition {
     fn eq(&self, other: &Self) -> bool {
         Python::attach(|py| {
-            self.paths == other.paths &&
-            self.options_reader.borrow(py).0 == other.options_reader.borrow(py).0
+            self.paths == other.paths
+                && self.options_reader.borrow(py).0 == other.options_reader.borrow(py).0
         })
     }
 }
@@ -368,3 +524,95 @@ impl PyNgSourcePartition {
         self.options_reader.borrow(py)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        append_suggestion, append_suggestion_if_known, damerau_levenshtein_distance,
+        suggest_option_name,
+    };
+
+    #[test]
+    fn damerau_levenshtein_distance_identical() {
+        assert_eq!(damerau_levenshtein_distance("timeout", "timeout"), 0);
+    }
+
+    #[test]
+    fn damerau_levenshtein_distance_substitution() {
+        assert_eq!(damerau_levenshtein_distance("timeout", "timeput"), 1);
+    }
+
+    #[test]
+    fn damerau_levenshtein_distance_transposition() {
+        assert_eq!(damerau_levenshtein_distance("timeout", "itmeout"), 1);
+    }
+
+    #[test]
+    fn damerau_levenshtein_distance_insertion_and_deletion() {
+        assert_eq!(damerau_levenshtein_distance("timeout", "timeouts"), 1);
+        assert_eq!(damerau_levenshtein_distance("timeouts", "timeout"), 1);
+    }
+
+    #[test]
+    fn suggest_option_name_finds_close_match() {
+        let known = ["timeout", "retries", "log-level"];
+        assert_eq!(
+            suggest_option_name("timeouts", known.iter().copied()),
+            Some("timeout".to_owned())
+        );
+    }
+
+    #[test]
+    fn suggest_option_name_ignores_distant_candidates() {
+        let known = ["log-level", "retries"];
+        assert_eq!(suggest_option_name("timeout", known.iter().copied()), None);
+    }
+
+    #[test]
+    fn suggest_option_name_ignores_exact_match() {
+        // An option that already matches exactly isn't "close" to itself in a way worth
+        // suggesting -- the caller only asks for a suggestion when parsing already failed.
+        let known = ["timeout"];
+        assert_eq!(suggest_option_name("timeout", known.iter().copied()), None);
+    }
+
+    #[test]
+    fn append_suggestion_appends_when_close_match_exists() {
+        let known = vec!["timeout".to_owned()];
+        let message = append_suggestion("unrecognized option".to_owned(), "timeouts", &known);
+        assert_eq!(
+            message,
+            "unrecognized option\n\nDid you mean `timeout`?".to_owned()
+        );
+    }
+
+    #[test]
+    fn append_suggestion_leaves_message_untouched_when_no_match() {
+        let known = vec!["log-level".to_owned()];
+        let message = append_suggestion("unrecognized option".to_owned(), "timeout", &known);
+        assert_eq!(message, "unrecognized option".to_owned());
+    }
+
+    #[test]
+    fn append_suggestion_if_known_leaves_message_untouched_when_not_supplied() {
+        // Existing callers that don't pass `name`/`known_option_names` (every caller that
+        // predates this feature) must get the original message back unchanged.
+        let message =
+            append_suggestion_if_known("unrecognized option".to_owned(), None, None);
+        assert_eq!(message, "unrecognized option".to_owned());
+    }
+
+    #[test]
+    fn append_suggestion_if_known_appends_when_both_supplied() {
+        let known = vec!["timeout".to_owned()];
+        let message = append_suggestion_if_known(
+            "unrecognized option".to_owned(),
+            Some("timeouts"),
+            Some(&known),
+        );
+        assert_eq!(
+            message,
+            "unrecognized option\n\nDid you mean `timeout`?".to_owned()
+        );
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2026 Pants project contributors (see CONTRIBUTORS.md).
+// Licensed under the Apache License, Version 2.0 (see LICENSE).
+
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::{Dir, File, Link, Stat};
+
+///
+/// Returns the path carried by `stat`, regardless of which variant it is. Kept as a free function
+/// (rather than an inherent method on `Stat`, which is defined elsewhere in this crate) so this
+/// module doesn't need to own `Stat`'s definition to use it.
+///
+fn stat_path(stat: &Stat) -> &Path {
+    match stat {
+        Stat::Dir(Dir(path)) => path.as_path(),
+        Stat::File(File { path, .. }) => path.as_path(),
+        Stat::Link(Link { path, .. }) => path.as_path(),
+    }
+}
+
+///
+/// Gitignore-style path excludes, as used by `PosixFS`/`WinFS` to implement `is_ignored`. Wraps
+/// the `ignore` crate's `Gitignore` matcher, which already implements the precedence rules of
+/// `.gitignore` (later patterns override earlier ones, `!` negates, etc.).
+///
+pub struct GitignoreStyleExcludes {
+    gitignore: Gitignore,
+    // A second matcher built from the same patterns, each lowercased, so that
+    // `is_ignored_case_insensitive` can fold the case of the queried path without also having to
+    // fold the case of every pattern on every call.
+    gitignore_lowercase: Gitignore,
+}
+
+impl GitignoreStyleExcludes {
+    pub fn create(patterns: &[String]) -> Result<Self, String> {
+        let build = |patterns: &[String]| -> Result<Gitignore, String> {
+            let mut builder = GitignoreBuilder::new("");
+            for pattern in patterns {
+                builder.add_line(None, pattern).map_err(|e| {
+                    format!("Could not parse glob exclude pattern {pattern:?}: {e}")
+                })?;
+            }
+            builder
+                .build()
+                .map_err(|e| format!("Could not build glob exclude matcher: {e}"))
+        };
+
+        let lowercase_patterns: Vec<String> =
+            patterns.iter().map(|pattern| pattern.to_lowercase()).collect();
+
+        Ok(Self {
+            gitignore: build(patterns)?,
+            gitignore_lowercase: build(&lowercase_patterns)?,
+        })
+    }
+
+    pub fn is_ignored(&self, stat: &Stat) -> bool {
+        Self::is_ignored_path(&self.gitignore, stat_path(stat), matches!(stat, Stat::Dir(_)))
+    }
+
+    ///
+    /// As `is_ignored`, but folds the case of both `stat`'s path and the configured patterns
+    /// before matching, to match NTFS's case-insensitive, case-preserving semantics: a pattern
+    /// like `Foo/bar` must also match `foo/BAR`.
+    ///
+    pub fn is_ignored_case_insensitive(&self, stat: &Stat) -> bool {
+        let lowercased = stat_path(stat).to_string_lossy().to_lowercase();
+        Self::is_ignored_path(
+            &self.gitignore_lowercase,
+            Path::new(&lowercased),
+            matches!(stat, Stat::Dir(_)),
+        )
+    }
+
+    fn is_ignored_path(gitignore: &Gitignore, path: &Path, is_dir: bool) -> bool {
+        gitignore.matched(path, is_dir).is_ignore()
+    }
+}
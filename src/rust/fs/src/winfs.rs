@@ -3,8 +3,10 @@
 
 use std::fs;
 use std::io::{self, ErrorKind};
+#[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -13,12 +15,266 @@ use crate::directory::SymlinkBehavior;
 use crate::gitignore::GitignoreStyleExcludes;
 use crate::{Dir, DirectoryListing, File, Link, PathMetadata, PathMetadataKind, Stat, Vfs};
 
+///
+/// The kind of filesystem-access operation recorded by an `FsAccessRecorder`.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FsAccessOp {
+    Scandir,
+    ReadLink,
+    StatSync,
+    PathMetadata,
+}
+
+///
+/// A single recorded filesystem access, carrying enough information to reconstruct an ordered,
+/// digest-able manifest of exactly which paths were consumed from a `WinFS` root, without
+/// capturing file contents.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FsAccessEvent {
+    /// Monotonically increasing within a single `WinFS` (and its clones), used to reconstruct the
+    /// order in which paths were accessed.
+    pub seq: u64,
+    pub path: PathBuf,
+    pub op: FsAccessOp,
+    pub kind: Option<PathMetadataKind>,
+    pub len: Option<u64>,
+    pub is_executable: Option<bool>,
+}
+
+///
+/// A sink for `FsAccessEvent`s. Implementations are expected to be cheap and non-blocking (e.g.
+/// appending to an in-memory log that can later be drained into a serialized manifest), since
+/// `record` is called inline with every `Vfs` operation, including the synchronous `stat_sync`.
+///
+pub trait FsAccessRecorder: Send + Sync {
+    fn record(&self, ev: FsAccessEvent);
+}
+
+///
+/// Returns the next sequence number from `counter`, incrementing it. A plain `Relaxed` fetch-add
+/// is sufficient (rather than e.g. a mutex-guarded counter): we only need a total order among the
+/// events seen by a single `FsAccessRecorder`, not synchronization with any other state.
+///
+fn next_seq(counter: &AtomicU64) -> u64 {
+    counter.fetch_add(1, Ordering::Relaxed)
+}
+
+///
+/// Whether a root is backed by local storage or by a network-backed filesystem (NFS, SMB/CIFS,
+/// FUSE, ...). Network mounts make per-entry `symlink_metadata` calls (as used by `scandir_sync`)
+/// both slow (an extra round trip per entry) and occasionally unreliable (stale file handles), so
+/// we probe for this once at construction and expose it via `fs_kind()`.
+///
+/// NB: `stat_sync` branches on this (retrying once on `ESTALE` for `FsKind::Network` roots), but
+/// that's the only access-pattern change so far -- batching or parallelizing per-entry stats
+/// would need to happen in `scandir_sync`, which isn't defined in this file, so that part remains
+/// a follow-up rather than something this probe alone delivers.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FsKind {
+    Local,
+    Network,
+}
+
+///
+/// `fs_kind_probe::probe` returns `None` on an unsupported platform or when the underlying syscall
+/// fails; this resolves that into the conservative default of `FsKind::Local`, which costs some
+/// performance on an unrecognized network mount but never silently changes correctness semantics
+/// (unlike guessing `Network` for something that's actually local).
+///
+fn resolve_fs_kind(probed: Option<FsKind>) -> FsKind {
+    probed.unwrap_or(FsKind::Local)
+}
+
+#[cfg(unix)]
+mod fs_kind_probe {
+    use super::FsKind;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    // See statfs(2)/the relevant kernel headers for the magic numbers of `f_type`.
+    const NFS_SUPER_MAGIC: i64 = 0x6969;
+    const SMB2_SUPER_MAGIC: i64 = 0xFE534D42u32 as i64;
+    const CIFS_SUPER_MAGIC: i64 = 0xFF534D42u32 as i64;
+    const FUSE_SUPER_MAGIC: i64 = 0x65735546;
+
+    pub fn probe(root: &Path) -> Option<FsKind> {
+        let c_path = std::ffi::CString::new(root.as_os_str().as_bytes()).ok()?;
+        let mut buf: libc::statfs = unsafe { std::mem::zeroed() };
+        let ret = unsafe { libc::statfs(c_path.as_ptr(), &mut buf) };
+        if ret != 0 {
+            return None;
+        }
+        let f_type = buf.f_type as i64;
+        Some(match f_type {
+            NFS_SUPER_MAGIC | SMB2_SUPER_MAGIC | CIFS_SUPER_MAGIC | FUSE_SUPER_MAGIC => {
+                FsKind::Network
+            }
+            // ext2/3/4, xfs, btrfs, tmpfs, and anything else we don't recognize: assume Local.
+            _ => FsKind::Local,
+        })
+    }
+}
+
+#[cfg(windows)]
+mod fs_kind_probe {
+    use super::FsKind;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+
+    const DRIVE_REMOTE: u32 = 4;
+
+    extern "system" {
+        fn GetDriveTypeW(lpRootPathName: *const u16) -> u32;
+    }
+
+    pub fn probe(root: &Path) -> Option<FsKind> {
+        // `GetDriveTypeW` wants a root path like `C:\` or `\\server\share\`; a trailing
+        // separator is required for UNC paths to be recognized correctly. Only the prefix and
+        // root-directory components of `root` are kept, so a directory below the volume root
+        // is probed by its volume.
+        let volume_root: std::path::PathBuf = root
+            .components()
+            .take_while(|c| {
+                matches!(c, std::path::Component::Prefix(_) | std::path::Component::RootDir)
+            })
+            .collect();
+        let mut wide: Vec<u16> = volume_root.as_os_str().encode_wide().collect();
+        if wide.last().copied() != Some(b'\\' as u16) {
+            wide.push(b'\\' as u16);
+        }
+        wide.push(0);
+        let drive_type = unsafe { GetDriveTypeW(wide.as_ptr()) };
+        Some(if drive_type == DRIVE_REMOTE {
+            FsKind::Network
+        } else {
+            FsKind::Local
+        })
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod fs_kind_probe {
+    use super::FsKind;
+    use std::path::Path;
+
+    pub fn probe(_root: &Path) -> Option<FsKind> {
+        None
+    }
+}
+
+///
+/// NTFS reparse points are the underlying mechanism for both symlinks and junctions (a.k.a.
+/// mount points): `std::fs::symlink_metadata`'s `FileType::is_symlink` is true for both, but only
+/// symlinks have POSIX-like semantics (they can be relative, point to files, etc.) -- a junction
+/// is always an absolute, volume-relative redirect to another directory. We distinguish the two
+/// by their reparse tag, `IO_REPARSE_TAG_SYMLINK` vs. `IO_REPARSE_TAG_MOUNT_POINT`.
+///
+#[cfg(windows)]
+mod reparse {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+
+    const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+    const IO_REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+    const INVALID_HANDLE_VALUE: isize = -1;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ReparseKind {
+        Symlink,
+        Junction,
+    }
+
+    #[repr(C)]
+    struct Win32FindDataW {
+        dw_file_attributes: u32,
+        ft_creation_time: [u32; 2],
+        ft_last_access_time: [u32; 2],
+        ft_last_write_time: [u32; 2],
+        n_file_size_high: u32,
+        n_file_size_low: u32,
+        // When `dw_file_attributes` has `FILE_ATTRIBUTE_REPARSE_POINT` set, this field is
+        // populated with the reparse tag instead of the usual "reserved" value.
+        dw_reserved0: u32,
+        dw_reserved1: u32,
+        c_file_name: [u16; 260],
+        c_alternate_file_name: [u16; 14],
+    }
+
+    extern "system" {
+        fn FindFirstFileW(
+            file_name: *const u16,
+            find_file_data: *mut Win32FindDataW,
+        ) -> *mut c_void;
+        fn FindClose(find_file: *mut c_void) -> i32;
+    }
+
+    /// Returns the reparse kind of `path`, or `None` if it isn't a reparse point we recognize (or
+    /// isn't a reparse point at all).
+    pub fn reparse_kind(path: &Path) -> Option<ReparseKind> {
+        let mut wide: Vec<u16> = path.as_os_str().encode_wide().collect();
+        wide.push(0);
+        let mut data: Win32FindDataW = unsafe { std::mem::zeroed() };
+        let handle = unsafe { FindFirstFileW(wide.as_ptr(), &mut data) };
+        if handle as isize == INVALID_HANDLE_VALUE {
+            return None;
+        }
+        unsafe { FindClose(handle) };
+        if data.dw_file_attributes & FILE_ATTRIBUTE_REPARSE_POINT == 0 {
+            return None;
+        }
+        match data.dw_reserved0 {
+            IO_REPARSE_TAG_SYMLINK => Some(ReparseKind::Symlink),
+            IO_REPARSE_TAG_MOUNT_POINT => Some(ReparseKind::Junction),
+            _ => None,
+        }
+    }
+
+    /// Junction targets are reported by `std::fs::read_link` as NT device paths (e.g.
+    /// `\??\C:\some\dir`); strip that prefix so the rest of the pipeline can treat the result as
+    /// an ordinary Windows path.
+    pub fn strip_nt_prefix(target: &Path) -> PathBuf {
+        match target.to_str().and_then(|s| s.strip_prefix(r"\??\")) {
+            Some(stripped) => PathBuf::from(stripped),
+            None => target.to_path_buf(),
+        }
+    }
+}
+
+///
+/// Whether `err` looks like a stale network file handle (`ESTALE` on unix; the closest Windows
+/// equivalent is a handle invalidated by the remote share, which surfaces as a generic I/O error
+/// without a dedicated `ErrorKind`, so we only special-case the unix errno here). This is the
+/// concrete, actually-reachable hazard the `FsKind::Network` doc comment above warns about: a
+/// handle that was valid when the containing directory was read can be invalidated by another
+/// client before we get around to stat-ing it.
+///
+fn is_stale_handle(err: &io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        err.raw_os_error() == Some(libc::ESTALE)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = err;
+        false
+    }
+}
+
 #[derive(Clone)]
 pub struct WinFS {
     root: Dir,
     ignore: Arc<GitignoreStyleExcludes>,
     executor: task_executor::Executor,
     symlink_behavior: SymlinkBehavior,
+    fs_kind: FsKind,
+    recorder: Option<Arc<dyn FsAccessRecorder>>,
+    recorder_seq: Arc<AtomicU64>,
+    case_insensitive: bool,
 }
 
 // Non-public functions used internally by the public functions below.
@@ -36,6 +292,44 @@ impl WinFS {
         ignorer: Arc<GitignoreStyleExcludes>,
         executor: task_executor::Executor,
         symlink_behavior: SymlinkBehavior,
+    ) -> Result<Self, String> {
+        Self::new_with_symlink_behavior_and_recorder(
+            root,
+            ignorer,
+            executor,
+            symlink_behavior,
+            None,
+        )
+    }
+
+    ///
+    /// As `new_with_symlink_behavior`, but additionally attaches an `FsAccessRecorder` that is
+    /// notified of every `scandir`, `read_link`, `stat_sync`, and `path_metadata` call made
+    /// through this `WinFS` (and any of its clones, which share the same recorder and sequence
+    /// counter).
+    ///
+    pub fn with_recorder<P: AsRef<Path>>(
+        root: P,
+        ignorer: Arc<GitignoreStyleExcludes>,
+        executor: task_executor::Executor,
+        symlink_behavior: SymlinkBehavior,
+        recorder: Arc<dyn FsAccessRecorder>,
+    ) -> Result<Self, String> {
+        Self::new_with_symlink_behavior_and_recorder(
+            root,
+            ignorer,
+            executor,
+            symlink_behavior,
+            Some(recorder),
+        )
+    }
+
+    fn new_with_symlink_behavior_and_recorder<P: AsRef<Path>>(
+        root: P,
+        ignorer: Arc<GitignoreStyleExcludes>,
+        executor: task_executor::Executor,
+        symlink_behavior: SymlinkBehavior,
+        recorder: Option<Arc<dyn FsAccessRecorder>>,
     ) -> Result<Self, String> {
         let root: &Path = root.as_ref();
         let canonical_root = root
@@ -54,27 +348,101 @@ impl WinFS {
             })
             .map_err(|e| format!("Could not canonicalize root {root:?}: {e:?}"))?;
 
+        // The kind of filesystem backing a root never changes for the lifetime of this struct,
+        // so we probe once here rather than on every scandir/stat.
+        let fs_kind = resolve_fs_kind(fs_kind_probe::probe(&canonical_root.0));
+
         Ok(Self {
             root: canonical_root,
             ignore: ignorer,
             executor: executor,
             symlink_behavior: symlink_behavior,
+            fs_kind,
+            recorder,
+            recorder_seq: Arc::new(AtomicU64::new(0)),
+            // NTFS is case-insensitive (but case-preserving) by default; everywhere else, paths
+            // are case-sensitive. Callers that know they're targeting a case-insensitive volume
+            // can opt in via `with_case_insensitive`.
+            case_insensitive: false,
         })
     }
+
+    ///
+    /// Builder-style opt-in for NTFS-style case-insensitive (but case-preserving) path comparison:
+    /// when set, two entries differing only in case are treated as the same path by `is_ignored`.
+    /// This does not affect `scandir`/`DirectoryListing`: entries that differ only in case are
+    /// still listed separately, since the underlying directory read reports whatever the
+    /// filesystem actually returns.
+    ///
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    ///
+    /// Records a filesystem access with the attached `FsAccessRecorder`, if any. A no-op when no
+    /// recorder was attached via `with_recorder`, so existing callers pay only the cost of an
+    /// `Option` check.
+    ///
+    fn record(
+        &self,
+        path: PathBuf,
+        op: FsAccessOp,
+        kind: Option<PathMetadataKind>,
+        len: Option<u64>,
+        is_executable: Option<bool>,
+    ) {
+        if let Some(recorder) = &self.recorder {
+            let seq = next_seq(&self.recorder_seq);
+            recorder.record(FsAccessEvent {
+                seq,
+                path,
+                op,
+                kind,
+                len,
+                is_executable,
+            });
+        }
+    }
 }
 
 // Public functions used externally.
 impl WinFS {
     pub async fn scandir(&self, dir_relative_to_root: Dir) -> Result<DirectoryListing, io::Error> {
         let vfs = self.clone();
-        self.executor
+        let dir_path = dir_relative_to_root.0.clone();
+        let result = self
+            .executor
             .spawn_blocking(move || vfs.scandir_sync(&dir_relative_to_root))
             .await?
-            .map_err(|e| io::Error::other(format!("Synchronous scandir failed: {e}")))
+            .map_err(|e| io::Error::other(format!("Synchronous scandir failed: {e}")));
+        if result.is_ok() {
+            self.record(
+                dir_path,
+                FsAccessOp::Scandir,
+                Some(PathMetadataKind::Directory),
+                None,
+                None,
+            );
+        }
+        result
     }
 
     pub fn is_ignored(&self, stat: &Stat) -> bool {
-        self.ignore.is_ignored(stat)
+        if self.case_insensitive {
+            // Matches NTFS's case-insensitive, case-preserving semantics: a pattern like
+            // `Foo/bar` must also match `foo/BAR`.
+            self.ignore.is_ignored_case_insensitive(stat)
+        } else {
+            self.ignore.is_ignored(stat)
+        }
+    }
+
+    ///
+    /// The kind of filesystem backing this root, as determined once at construction time.
+    ///
+    pub fn fs_kind(&self) -> FsKind {
+        self.fs_kind
     }
 
     pub fn file_path(&self, file: &File) -> PathBuf {
@@ -82,9 +450,40 @@ impl WinFS {
     }
 
     pub async fn read_link(&self, link: &Link) -> Result<PathBuf, io::Error> {
-        let link_parent = link.path.parent().map(Path::to_owned);
         let link_abs = self.root.0.join(link.path.as_path());
-        tokio::fs::read_link(&link_abs)
+
+        // Junctions, unlike symlinks, always resolve relative to the volume, not to the link's
+        // parent directory -- so we special-case them before falling into the generic
+        // (relative-to-parent) resolution used for ordinary symlinks below.
+        #[cfg(windows)]
+        if reparse::reparse_kind(&link_abs) == Some(reparse::ReparseKind::Junction) {
+            let raw_target = tokio::fs::read_link(&link_abs).await.map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Failed to read junction {link_abs:?}: {e}"),
+                )
+            })?;
+            let target = reparse::strip_nt_prefix(&raw_target);
+            let relative_target = target.strip_prefix(&self.root.0).map(Path::to_owned).map_err(
+                |_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Junction escapes root: {target:?}"),
+                    )
+                },
+            )?;
+            self.record(
+                link.path.clone(),
+                FsAccessOp::ReadLink,
+                Some(PathMetadataKind::Junction),
+                None,
+                None,
+            );
+            return Ok(relative_target);
+        }
+
+        let link_parent = link.path.parent().map(Path::to_owned);
+        let result = tokio::fs::read_link(&link_abs)
             .await
             .and_then(|path_buf| {
                 if path_buf.is_absolute() {
@@ -103,7 +502,19 @@ impl WinFS {
                         })
                 }
             })
-            .map_err(|e| io::Error::new(e.kind(), format!("Failed to read link {link_abs:?}: {e}")))
+            .map_err(|e| {
+                io::Error::new(e.kind(), format!("Failed to read link {link_abs:?}: {e}"))
+            });
+        if result.is_ok() {
+            self.record(
+                link.path.clone(),
+                FsAccessOp::ReadLink,
+                Some(PathMetadataKind::Symlink),
+                None,
+                None,
+            );
+        }
+        result
     }
 
     ///
@@ -111,7 +522,25 @@ impl WinFS {
     ///
     /// NB: This method is synchronous because it is used to stat all files in a directory as one
     /// blocking operation as part of `scandir_sync` (as recommended by the `tokio` documentation, to
-    /// avoid many small spawned tasks).
+    /// avoid many small spawned tasks). Symlink targets themselves are never read here: `read_link`
+    /// is only called lazily, when a caller actually asks for one.
+    ///
+    /// NB: on a `FsKind::Network` root we still use `fs::symlink_metadata` whenever
+    /// `symlink_behavior` is `Aware`, since switching to `fs::metadata` would silently dereference
+    /// symlinks and report them as whatever they point to (stop reporting `Stat::Link` at all),
+    /// changing what "Aware" means. What `fs_kind()` *does* change here is resilience: network
+    /// mounts are prone to `ESTALE` on a handle that was valid moments ago (e.g. another client
+    /// renamed the file between the directory read and this stat), so on a `FsKind::Network` root
+    /// we retry the stat once on `ESTALE` before giving up, which isn't worth doing (or safe to
+    /// do silently) on local filesystems where a stale handle usually means something is actually
+    /// wrong. Batching many entries' stats into fewer network round trips would need to happen in
+    /// `scandir_sync`, which isn't defined in this file.
+    ///
+    /// NB: `Stat` has no separate representation for NTFS junctions -- `PosixFS::stat_internal`
+    /// classifies any reparse point as a symlink, matching `FileType::is_symlink`. Callers that
+    /// need to distinguish a junction from a true symlink (e.g. to resolve its target relative to
+    /// the volume rather than to the link's parent) should use `path_metadata`, whose
+    /// `PathMetadataKind::Junction` makes that distinction explicit.
     ///
     pub fn stat_sync(&self, relative_path: &Path) -> Result<Option<Stat>, io::Error> {
         if cfg!(debug_assertions) && relative_path.is_absolute() {
@@ -123,18 +552,42 @@ impl WinFS {
             ));
         }
         let abs_path = self.root.0.join(relative_path);
-        let metadata = match self.symlink_behavior {
+        let stat_once = || match self.symlink_behavior {
             SymlinkBehavior::Aware => fs::symlink_metadata(&abs_path),
             SymlinkBehavior::Oblivious => fs::metadata(&abs_path),
         };
-        metadata
+        let metadata = match stat_once() {
+            Err(err) if self.fs_kind == FsKind::Network && is_stale_handle(&err) => stat_once(),
+            other => other,
+        };
+        let result = metadata
             .and_then(|metadata| {
                 PosixFS::stat_internal(&abs_path, metadata.file_type(), || Ok(metadata))
             })
             .or_else(|err| match err.kind() {
                 io::ErrorKind::NotFound => Ok(None),
                 _ => Err(err),
-            })
+            });
+        // Recorded with an atomic sequence number (rather than relying on e.g. a mutex-guarded
+        // `Vec`) because this method is also called synchronously, per-entry, from inside the
+        // single blocking closure backing `scandir_sync`. Not recorded for a path that doesn't
+        // exist: there's no resulting kind to report, so an event here would just be a blank
+        // entry in the manifest.
+        if let Ok(Some(stat)) = &result {
+            let kind = match stat {
+                Stat::Dir(_) => PathMetadataKind::Directory,
+                Stat::File(_) => PathMetadataKind::File,
+                Stat::Link(_) => PathMetadataKind::Symlink,
+            };
+            self.record(
+                relative_path.to_path_buf(),
+                FsAccessOp::StatSync,
+                Some(kind),
+                None,
+                None,
+            );
+        }
+        result
     }
 
     pub async fn path_metadata(&self, path: PathBuf) -> Result<Option<PathMetadata>, io::Error> {
@@ -142,6 +595,17 @@ impl WinFS {
         match tokio::fs::symlink_metadata(&abs_path).await {
             Ok(metadata) => {
                 let (kind, symlink_target) = match metadata.file_type() {
+                    #[cfg(windows)]
+                    ft if ft.is_symlink()
+                        && reparse::reparse_kind(&abs_path)
+                            == Some(reparse::ReparseKind::Junction) =>
+                    {
+                        let raw_target = tokio::fs::read_link(&abs_path).await.map_err(|e| io::Error::other(format!("path {abs_path:?} was previously a junction but read_link failed: {e}")))?;
+                        (
+                            PathMetadataKind::Junction,
+                            Some(reparse::strip_nt_prefix(&raw_target)),
+                        )
+                    }
                     ft if ft.is_symlink() => {
                         let symlink_target = tokio::fs::read_link(&abs_path).await.map_err(|e| io::Error::other(format!("path {abs_path:?} was previously a symlink but read_link failed: {e}")))?;
                         (PathMetadataKind::Symlink, Some(symlink_target))
@@ -156,6 +620,18 @@ impl WinFS {
                     let mode = metadata.permissions().mode();
                     (Some(mode), (mode & 0o111) != 0)
                 };
+                // NTFS has no executable bit: executability is inferred by extension elsewhere
+                // (e.g. `.exe`, `.bat`), not from filesystem metadata.
+                #[cfg(windows)]
+                let (unix_mode, is_executable) = (None, false);
+
+                self.record(
+                    path.clone(),
+                    FsAccessOp::PathMetadata,
+                    Some(kind),
+                    Some(metadata.len()),
+                    Some(is_executable),
+                );
 
                 Ok(Some(PathMetadata {
                     path,
@@ -178,7 +654,7 @@ impl WinFS {
 #[async_trait]
 impl Vfs<io::Error> for Arc<WinFS> {
     async fn read_link(&self, link: &Link) -> Result<PathBuf, io::Error> {
-        PosixFS::read_link(self, link).await
+        WinFS::read_link(self, link).await
     }
 
     async fn scandir(&self, dir: Dir) -> Result<Arc<DirectoryListing>, io::Error> {
@@ -197,3 +673,43 @@ impl Vfs<io::Error> for Arc<WinFS> {
         io::Error::other(msg)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_stale_handle, next_seq, resolve_fs_kind, FsKind};
+    use std::io;
+    use std::sync::atomic::AtomicU64;
+
+    #[test]
+    #[cfg(unix)]
+    fn is_stale_handle_recognizes_estale() {
+        let err = io::Error::from_raw_os_error(libc::ESTALE);
+        assert!(is_stale_handle(&err));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn is_stale_handle_rejects_other_errors() {
+        let err = io::Error::from(io::ErrorKind::NotFound);
+        assert!(!is_stale_handle(&err));
+    }
+
+    #[test]
+    fn resolve_fs_kind_passes_through_a_successful_probe() {
+        assert_eq!(resolve_fs_kind(Some(FsKind::Network)), FsKind::Network);
+        assert_eq!(resolve_fs_kind(Some(FsKind::Local)), FsKind::Local);
+    }
+
+    #[test]
+    fn resolve_fs_kind_defaults_to_local_when_the_probe_is_inconclusive() {
+        assert_eq!(resolve_fs_kind(None), FsKind::Local);
+    }
+
+    #[test]
+    fn next_seq_increments_monotonically_from_zero() {
+        let counter = AtomicU64::new(0);
+        assert_eq!(next_seq(&counter), 0);
+        assert_eq!(next_seq(&counter), 1);
+        assert_eq!(next_seq(&counter), 2);
+    }
+}